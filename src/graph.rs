@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+
+/// A handle into a `Graph`'s node arena.
+///
+/// `Idx` is `Copy` so expressions can be wired together freely: building
+/// `a * b` and `a * c` from the same `a: Idx` makes both `Mul` nodes point
+/// at the same arena slot instead of cloning the subtree, so `A*B + A*C`
+/// only ever has one `A` node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Idx(usize);
+
+#[derive(Debug, Clone)]
+enum Node {
+    Const(f64),
+    Var(String),
+    Add(Idx, Idx),
+    Sub(Idx, Idx),
+    Mul(Idx, Idx),
+    Div(Idx, Idx),
+    Pow(Idx, f64),
+    Exp(Idx),
+    Ln(Idx),
+    Sin(Idx),
+    Cos(Idx),
+    Sigmoid(Idx),
+    Relu(Idx),
+}
+
+/// An arena of computation nodes forming a DAG.
+///
+/// Nodes are appended as expressions are built, and every `Idx` an
+/// expression holds refers to a node that already exists in `nodes`, so
+/// node `i`'s inputs always have an index less than `i`. That lets
+/// `compute_values` evaluate the whole graph with a single forward scan
+/// instead of re-walking shared subexpressions once per parent.
+#[derive(Debug)]
+pub struct Graph {
+    nodes: Vec<Node>,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph { nodes: Vec::new() }
+    }
+
+    fn push(&mut self, node: Node) -> Idx {
+        let idx = Idx(self.nodes.len());
+        self.nodes.push(node);
+        idx
+    }
+
+    pub fn constant(&mut self, val: f64) -> Idx {
+        self.push(Node::Const(val))
+    }
+
+    /// Adds a variable node. Repeated calls with the same `name` each
+    /// create a new node; callers that want sharing (e.g. the parser)
+    /// should look up and reuse an existing `Idx` themselves.
+    pub fn var(&mut self, name: &str) -> Idx {
+        self.push(Node::Var(String::from(name)))
+    }
+
+    pub fn add(&mut self, lhs: Idx, rhs: Idx) -> Idx {
+        self.push(Node::Add(lhs, rhs))
+    }
+
+    pub fn sub(&mut self, lhs: Idx, rhs: Idx) -> Idx {
+        self.push(Node::Sub(lhs, rhs))
+    }
+
+    pub fn mul(&mut self, lhs: Idx, rhs: Idx) -> Idx {
+        self.push(Node::Mul(lhs, rhs))
+    }
+
+    pub fn div(&mut self, lhs: Idx, rhs: Idx) -> Idx {
+        self.push(Node::Div(lhs, rhs))
+    }
+
+    pub fn pow(&mut self, base: Idx, exponent: f64) -> Idx {
+        self.push(Node::Pow(base, exponent))
+    }
+
+    pub fn exp(&mut self, x: Idx) -> Idx {
+        self.push(Node::Exp(x))
+    }
+
+    pub fn ln(&mut self, x: Idx) -> Idx {
+        self.push(Node::Ln(x))
+    }
+
+    pub fn sin(&mut self, x: Idx) -> Idx {
+        self.push(Node::Sin(x))
+    }
+
+    pub fn cos(&mut self, x: Idx) -> Idx {
+        self.push(Node::Cos(x))
+    }
+
+    pub fn sigmoid(&mut self, x: Idx) -> Idx {
+        self.push(Node::Sigmoid(x))
+    }
+
+    pub fn relu(&mut self, x: Idx) -> Idx {
+        self.push(Node::Relu(x))
+    }
+
+    /// Evaluates every node exactly once in index order (which is always
+    /// a valid topological order, since a node's inputs are created
+    /// before the node itself), resolving `Var` nodes through
+    /// `var_value`, and returns the memoized value of each.
+    fn eval(&self, var_value: impl Fn(&str) -> f64) -> Vec<f64> {
+        let mut values: Vec<f64> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let value = match node {
+                Node::Const(v) => *v,
+                Node::Var(name) => var_value(name),
+                Node::Add(lhs, rhs) => values[lhs.0] + values[rhs.0],
+                Node::Sub(lhs, rhs) => values[lhs.0] - values[rhs.0],
+                Node::Mul(lhs, rhs) => values[lhs.0] * values[rhs.0],
+                Node::Div(lhs, rhs) => values[lhs.0] / values[rhs.0],
+                Node::Pow(base, exponent) => values[base.0].powf(*exponent),
+                Node::Exp(x) => values[x.0].exp(),
+                Node::Ln(x) => values[x.0].ln(),
+                Node::Sin(x) => values[x.0].sin(),
+                Node::Cos(x) => values[x.0].cos(),
+                Node::Sigmoid(x) => 1_f64 / (1_f64 + (-values[x.0]).exp()),
+                Node::Relu(x) => values[x.0].max(0_f64),
+            };
+            values.push(value);
+        }
+        values
+    }
+
+    /// Evaluates the graph with every `Var` node treated as 0. Useful
+    /// when an expression is built entirely from constants.
+    pub fn compute_values(&self) -> Vec<f64> {
+        self.eval(|_| 0_f64)
+    }
+
+    /// Evaluates the graph, substituting a value for each named `Var`
+    /// node looked up in `env`. Panics if a variable is referenced that
+    /// is missing from `env`.
+    pub fn compute_values_with(&self, env: &HashMap<String, f64>) -> Vec<f64> {
+        self.eval(|name| *env.get(name).unwrap_or_else(|| panic!("Not found: {:?}", name)))
+    }
+
+    pub fn value_of(&self, values: &[f64], idx: Idx) -> f64 {
+        values[idx.0]
+    }
+
+    /// Returns whether any `Var` node is reachable from `idx`, used by the
+    /// parser to decide whether an exponent subexpression (e.g. `(1 + 1)`
+    /// or a bare literal) can be constant-folded for `^`.
+    pub(crate) fn contains_var(&self, idx: Idx) -> bool {
+        match &self.nodes[idx.0] {
+            Node::Const(_) => false,
+            Node::Var(_) => true,
+            Node::Add(lhs, rhs) | Node::Sub(lhs, rhs) | Node::Mul(lhs, rhs) | Node::Div(lhs, rhs) => {
+                self.contains_var(*lhs) || self.contains_var(*rhs)
+            }
+            Node::Pow(base, _) => self.contains_var(*base),
+            Node::Exp(x) | Node::Ln(x) | Node::Sin(x) | Node::Cos(x) | Node::Sigmoid(x)
+            | Node::Relu(x) => self.contains_var(*x),
+        }
+    }
+
+    /// Parses `input` into a fresh graph. See [`crate::parser::parse`].
+    pub fn parse(input: &str) -> Result<(Graph, Idx), crate::parser::ParseError> {
+        crate::parser::parse(input)
+    }
+
+    /// Runs reverse-mode autodiff from `output`, given the forward-pass
+    /// `values` computed by `compute_values`/`compute_values_with`.
+    ///
+    /// Walks nodes in reverse index order (the reverse of the forward
+    /// topological order), seeding `output`'s adjoint to 1.0 and
+    /// distributing each node's adjoint to its inputs through the local
+    /// partial derivative. Adjoints are *accumulated* rather than
+    /// overwritten, since a node reused by several consumers (e.g. the
+    /// shared `A` in `A*B + A*C`) picks up a contribution from each one.
+    /// Returns the gradient of `output` with respect to every variable
+    /// name that appears in the graph.
+    pub fn backward(&self, values: &[f64], output: Idx) -> HashMap<String, f64> {
+        let mut adjoints = vec![0_f64; self.nodes.len()];
+        adjoints[output.0] = 1_f64;
+        let mut grads: HashMap<String, f64> = HashMap::new();
+
+        for i in (0..self.nodes.len()).rev() {
+            let adj = adjoints[i];
+            match &self.nodes[i] {
+                Node::Const(_) => {}
+                Node::Var(name) => {
+                    *grads.entry(name.clone()).or_insert(0_f64) += adj;
+                }
+                Node::Add(lhs, rhs) => {
+                    adjoints[lhs.0] += adj;
+                    adjoints[rhs.0] += adj;
+                }
+                Node::Sub(lhs, rhs) => {
+                    adjoints[lhs.0] += adj;
+                    adjoints[rhs.0] -= adj;
+                }
+                Node::Mul(lhs, rhs) => {
+                    adjoints[lhs.0] += adj * values[rhs.0];
+                    adjoints[rhs.0] += adj * values[lhs.0];
+                }
+                Node::Div(lhs, rhs) => {
+                    adjoints[lhs.0] += adj / values[rhs.0];
+                    adjoints[rhs.0] += adj * (-values[lhs.0] / (values[rhs.0] * values[rhs.0]));
+                }
+                Node::Pow(base, exponent) => {
+                    adjoints[base.0] += adj * exponent * values[base.0].powf(exponent - 1_f64);
+                }
+                Node::Exp(x) => {
+                    adjoints[x.0] += adj * values[i];
+                }
+                Node::Ln(x) => {
+                    adjoints[x.0] += adj / values[x.0];
+                }
+                Node::Sin(x) => {
+                    adjoints[x.0] += adj * values[x.0].cos();
+                }
+                Node::Cos(x) => {
+                    adjoints[x.0] -= adj * values[x.0].sin();
+                }
+                Node::Sigmoid(x) => {
+                    let s = values[i];
+                    adjoints[x.0] += adj * s * (1_f64 - s);
+                }
+                Node::Relu(x) => {
+                    if values[x.0] > 0_f64 {
+                        adjoints[x.0] += adj;
+                    }
+                }
+            }
+        }
+
+        grads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_subexpressions() {
+        let mut g = Graph::new();
+        let a = g.constant(10_f64);
+        let b = g.constant(5_f64);
+        let c = g.constant(20_f64);
+        let t1 = g.mul(a, b);
+        let t2 = g.mul(a, c);
+        let out = g.add(t1, t2);
+
+        let values = g.compute_values();
+        assert_eq!(g.value_of(&values, out), 10_f64 * 5_f64 + 10_f64 * 20_f64);
+    }
+
+    #[test]
+    fn accumulates_gradient_for_shared_variable() {
+        // A*B + A*C, gradient w.r.t. A is B + C, not just the first branch.
+        let mut g = Graph::new();
+        let a = g.var("A");
+        let b = g.var("B");
+        let c = g.var("C");
+        let t1 = g.mul(a, b);
+        let t2 = g.mul(a, c);
+        let out = g.add(t1, t2);
+
+        let mut env = HashMap::new();
+        env.insert(String::from("A"), 2_f64);
+        env.insert(String::from("B"), 3_f64);
+        env.insert(String::from("C"), 4_f64);
+
+        let values = g.compute_values_with(&env);
+        let grads = g.backward(&values, out);
+
+        assert_eq!(grads[&String::from("A")], 3_f64 + 4_f64);
+        assert_eq!(grads[&String::from("B")], 2_f64);
+        assert_eq!(grads[&String::from("C")], 2_f64);
+    }
+
+    #[test]
+    fn differentiates_div_and_pow() {
+        // f = x / y + x^3
+        let mut g = Graph::new();
+        let x = g.var("x");
+        let y = g.var("y");
+        let quotient = g.div(x, y);
+        let cube = g.pow(x, 3_f64);
+        let out = g.add(quotient, cube);
+
+        let mut env = HashMap::new();
+        env.insert(String::from("x"), 2_f64);
+        env.insert(String::from("y"), 4_f64);
+
+        let values = g.compute_values_with(&env);
+        let grads = g.backward(&values, out);
+
+        assert_eq!(g.value_of(&values, out), 2_f64 / 4_f64 + 2_f64.powf(3_f64));
+        assert_eq!(grads[&String::from("x")], 1_f64 / 4_f64 + 3_f64 * 2_f64.powf(2_f64));
+        assert_eq!(grads[&String::from("y")], -2_f64 / (4_f64 * 4_f64));
+    }
+
+    #[test]
+    fn differentiates_sigmoid_and_relu() {
+        let mut g = Graph::new();
+        let x = g.var("x");
+        let s = g.sigmoid(x);
+        let r = g.relu(x);
+        let out = g.add(s, r);
+
+        let mut env = HashMap::new();
+        env.insert(String::from("x"), 0_f64);
+
+        let values = g.compute_values_with(&env);
+        let grads = g.backward(&values, out);
+
+        // sigmoid(0) = 0.5, its derivative is 0.5*(1-0.5) = 0.25;
+        // relu(0) is the boundary, defined here as a 0 subgradient.
+        assert_eq!(grads[&String::from("x")], 0.25_f64);
+    }
+
+    #[test]
+    fn differentiates_exp_ln_and_cos() {
+        // f = exp(x) + ln(x) + cos(x)
+        let mut g = Graph::new();
+        let x = g.var("x");
+        let e = g.exp(x);
+        let l = g.ln(x);
+        let c = g.cos(x);
+        let sum = g.add(e, l);
+        let out = g.add(sum, c);
+
+        let mut env = HashMap::new();
+        env.insert(String::from("x"), 2_f64);
+
+        let values = g.compute_values_with(&env);
+        let grads = g.backward(&values, out);
+
+        assert_eq!(
+            g.value_of(&values, out),
+            2_f64.exp() + 2_f64.ln() + 2_f64.cos()
+        );
+        assert_eq!(
+            grads[&String::from("x")],
+            2_f64.exp() + 1_f64 / 2_f64 - 2_f64.sin()
+        );
+    }
+
+    #[test]
+    fn evaluates_vars_from_env() {
+        let mut g = Graph::new();
+        let a = g.var("A");
+        let b = g.var("B");
+        let out = g.add(a, b);
+
+        let mut env = std::collections::HashMap::new();
+        env.insert(String::from("A"), 2_f64);
+        env.insert(String::from("B"), 3_f64);
+
+        let values = g.compute_values_with(&env);
+        assert_eq!(g.value_of(&values, out), 5_f64);
+    }
+}
@@ -0,0 +1,118 @@
+//! Gradient-descent training loop built on top of `Graph` and its
+//! reverse-mode backward pass.
+
+use std::collections::HashMap;
+
+use crate::graph::{Graph, Idx};
+
+/// Tunables for an `Optimizer` run.
+#[derive(Debug, Clone, Copy)]
+pub struct OptimizerConfig {
+    pub learning_rate: f64,
+    pub max_iterations: usize,
+    /// Stop early once the loss changes by less than this between
+    /// consecutive iterations.
+    pub tolerance: f64,
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> OptimizerConfig {
+        OptimizerConfig {
+            learning_rate: 0.01,
+            max_iterations: 1000,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+/// The result of an `Optimizer::minimize` run.
+#[derive(Debug, Clone)]
+pub struct OptimizationResult {
+    /// The final value of every variable referenced in the graph's
+    /// environment, including the trained ones.
+    pub variables: HashMap<String, f64>,
+    /// The loss at the start of each iteration, in order.
+    pub loss_history: Vec<f64>,
+}
+
+/// Drives a set of trainable variables toward a minimum of a loss node
+/// by repeated forward/backward passes over the same `Graph`.
+pub struct Optimizer {
+    config: OptimizerConfig,
+}
+
+impl Optimizer {
+    pub fn new(config: OptimizerConfig) -> Optimizer {
+        Optimizer { config }
+    }
+
+    /// Minimizes `loss` by updating the variables named in `trainable`,
+    /// starting from the values in `env`. Each iteration runs the forward
+    /// pass to get the loss, the backward pass to get its gradients, then
+    /// applies `v -= learning_rate * grad[v]` to every trainable `v`.
+    /// Stops after `max_iterations` or once the loss stops moving by more
+    /// than `tolerance`.
+    pub fn minimize(
+        &self,
+        graph: &Graph,
+        loss: Idx,
+        mut env: HashMap<String, f64>,
+        trainable: &[String],
+    ) -> OptimizationResult {
+        let mut loss_history = Vec::new();
+        let mut prev_loss: Option<f64> = None;
+
+        for _ in 0..self.config.max_iterations {
+            let values = graph.compute_values_with(&env);
+            let loss_value = graph.value_of(&values, loss);
+            loss_history.push(loss_value);
+
+            if let Some(prev) = prev_loss {
+                if (prev - loss_value).abs() < self.config.tolerance {
+                    break;
+                }
+            }
+            prev_loss = Some(loss_value);
+
+            let grads = graph.backward(&values, loss);
+            for name in trainable {
+                let grad = grads.get(name).copied().unwrap_or(0_f64);
+                let value = env.entry(name.clone()).or_insert(0_f64);
+                *value -= self.config.learning_rate * grad;
+            }
+        }
+
+        OptimizationResult {
+            variables: env,
+            loss_history,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimizes_a_simple_quadratic() {
+        // loss = (x - 3)^2, minimized at x = 3.
+        let mut g = Graph::new();
+        let x = g.var("x");
+        let target = g.constant(3_f64);
+        let diff = g.sub(x, target);
+        let loss = g.pow(diff, 2_f64);
+
+        let mut env = HashMap::new();
+        env.insert(String::from("x"), 0_f64);
+
+        let optimizer = Optimizer::new(OptimizerConfig {
+            learning_rate: 0.1,
+            max_iterations: 500,
+            tolerance: 1e-10,
+        });
+        let result = optimizer.minimize(&g, loss, env.clone(), &[String::from("x")]);
+
+        assert!((result.variables[&String::from("x")] - 3_f64).abs() < 1e-3);
+        assert!(result.loss_history.last().unwrap() < result.loss_history.first().unwrap());
+    }
+}
@@ -0,0 +1,300 @@
+//! A small recursive-descent parser that builds a `Graph` from a textual
+//! expression, so callers can evaluate and differentiate expressions
+//! supplied at runtime instead of hand-assembling nodes in Rust.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::graph::{Graph, Idx};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnknownFunction(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedChar(c) => write!(f, "unexpected character: {:?}", c),
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseError::UnexpectedToken(t) => write!(f, "unexpected token: {:?}", t),
+            ParseError::UnknownFunction(name) => write!(f, "unknown function: {:?}", name),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| ParseError::UnexpectedToken(text.clone()))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let token = match c {
+                '+' => Token::Plus,
+                '-' => Token::Minus,
+                '*' => Token::Star,
+                '/' => Token::Slash,
+                '^' => Token::Caret,
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                other => return Err(ParseError::UnexpectedChar(other)),
+            };
+            tokens.push(token);
+            i += 1;
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    graph: &'a mut Graph,
+    vars: HashMap<String, Idx>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), ParseError> {
+        match self.next() {
+            Some(ref t) if t == expected => Ok(()),
+            Some(t) => Err(ParseError::UnexpectedToken(format!("{:?}", t))),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    /// `expr := term (('+' | '-') term)*`
+    fn parse_expr(&mut self) -> Result<Idx, ParseError> {
+        let mut lhs = self.parse_term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Plus) => {
+                    self.next();
+                    let rhs = self.parse_term()?;
+                    lhs = self.graph.add(lhs, rhs);
+                }
+                Some(Token::Minus) => {
+                    self.next();
+                    let rhs = self.parse_term()?;
+                    lhs = self.graph.sub(lhs, rhs);
+                }
+                _ => break,
+            }
+        }
+        Ok(lhs)
+    }
+
+    /// `term := power (('*' | '/') power)*`
+    fn parse_term(&mut self) -> Result<Idx, ParseError> {
+        let mut lhs = self.parse_power()?;
+        loop {
+            match self.peek() {
+                Some(Token::Star) => {
+                    self.next();
+                    let rhs = self.parse_power()?;
+                    lhs = self.graph.mul(lhs, rhs);
+                }
+                Some(Token::Slash) => {
+                    self.next();
+                    let rhs = self.parse_power()?;
+                    lhs = self.graph.div(lhs, rhs);
+                }
+                _ => break,
+            }
+        }
+        Ok(lhs)
+    }
+
+    /// `power := unary ('^' power)?` (right-associative)
+    fn parse_power(&mut self) -> Result<Idx, ParseError> {
+        let base = self.parse_unary()?;
+        if let Some(Token::Caret) = self.peek() {
+            self.next();
+            let exponent_idx = self.parse_power()?;
+            if self.graph.contains_var(exponent_idx) {
+                return Err(ParseError::UnexpectedToken(String::from(
+                    "^ requires a constant exponent",
+                )));
+            }
+            let values = self.graph.compute_values();
+            let exponent = self.graph.value_of(&values, exponent_idx);
+            return Ok(self.graph.pow(base, exponent));
+        }
+        Ok(base)
+    }
+
+    /// `unary := '-' unary | atom`
+    fn parse_unary(&mut self) -> Result<Idx, ParseError> {
+        if let Some(Token::Minus) = self.peek() {
+            self.next();
+            let operand = self.parse_unary()?;
+            let zero = self.graph.constant(0_f64);
+            return Ok(self.graph.sub(zero, operand));
+        }
+        self.parse_atom()
+    }
+
+    /// `atom := number | ident '(' expr ')' | ident | '(' expr ')'`
+    fn parse_atom(&mut self) -> Result<Idx, ParseError> {
+        match self.next() {
+            Some(Token::Number(v)) => Ok(self.graph.constant(v)),
+            Some(Token::Ident(name)) => {
+                if let Some(Token::LParen) = self.peek() {
+                    self.next();
+                    let arg = self.parse_expr()?;
+                    self.expect(&Token::RParen)?;
+                    self.apply_function(&name, arg)
+                } else if let Some(&idx) = self.vars.get(&name) {
+                    Ok(idx)
+                } else {
+                    let idx = self.graph.var(&name);
+                    self.vars.insert(name, idx);
+                    Ok(idx)
+                }
+            }
+            Some(Token::LParen) => {
+                let inner = self.parse_expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(inner)
+            }
+            Some(t) => Err(ParseError::UnexpectedToken(format!("{:?}", t))),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    fn apply_function(&mut self, name: &str, arg: Idx) -> Result<Idx, ParseError> {
+        match name {
+            "sin" => Ok(self.graph.sin(arg)),
+            "cos" => Ok(self.graph.cos(arg)),
+            "exp" => Ok(self.graph.exp(arg)),
+            "ln" => Ok(self.graph.ln(arg)),
+            "sigmoid" => Ok(self.graph.sigmoid(arg)),
+            "relu" => Ok(self.graph.relu(arg)),
+            other => Err(ParseError::UnknownFunction(String::from(other))),
+        }
+    }
+}
+
+/// Parses `input` into a fresh `Graph`, returning the output node. Repeated
+/// identifiers are deduplicated to the same `Idx`, so e.g. `"a*b + a*c"`
+/// shares one `a` node and gets a correct gradient w.r.t. `a`.
+pub fn parse(input: &str) -> Result<(Graph, Idx), ParseError> {
+    let tokens = tokenize(input)?;
+    let mut graph = Graph::new();
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        graph: &mut graph,
+        vars: HashMap::new(),
+    };
+    let output = parser.parse_expr()?;
+    match parser.peek() {
+        None => Ok((graph, output)),
+        Some(t) => Err(ParseError::UnexpectedToken(format!("{:?}", t))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_precedence_and_shares_variables() {
+        let (g, out) = parse("2 * a + a * b").unwrap();
+        let mut env = HashMap::new();
+        env.insert(String::from("a"), 3_f64);
+        env.insert(String::from("b"), 4_f64);
+
+        let values = g.compute_values_with(&env);
+        let grads = g.backward(&values, out);
+
+        assert_eq!(g.value_of(&values, out), 2_f64 * 3_f64 + 3_f64 * 4_f64);
+        assert_eq!(grads[&String::from("a")], 2_f64 + 4_f64);
+    }
+
+    #[test]
+    fn parses_unary_functions_and_parens() {
+        let (g, out) = parse("sin(x) + relu(-2)").unwrap();
+        let mut env = HashMap::new();
+        env.insert(String::from("x"), 0_f64);
+
+        let values = g.compute_values_with(&env);
+        assert_eq!(g.value_of(&values, out), 0_f64.sin());
+    }
+
+    #[test]
+    fn folds_constant_exponent_subexpressions() {
+        let (g, out) = parse("2 ^ -1").unwrap();
+        let values = g.compute_values();
+        assert_eq!(g.value_of(&values, out), 2_f64.powf(-1_f64));
+
+        let (g, out) = parse("x ^ (1 + 1)").unwrap();
+        let mut env = HashMap::new();
+        env.insert(String::from("x"), 3_f64);
+        let values = g.compute_values_with(&env);
+        assert_eq!(g.value_of(&values, out), 3_f64.powf(2_f64));
+    }
+
+    #[test]
+    fn rejects_variable_exponent() {
+        let err = parse("2 ^ x").unwrap_err();
+        assert_eq!(
+            err,
+            ParseError::UnexpectedToken(String::from("^ requires a constant exponent"))
+        );
+    }
+
+    #[test]
+    fn reports_unknown_function() {
+        let err = parse("tan(x)").unwrap_err();
+        assert_eq!(err, ParseError::UnknownFunction(String::from("tan")));
+    }
+}